@@ -1,16 +1,182 @@
-use std::sync::Arc;
 use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// High-dynamic-range histogram for recording latencies in nanoseconds.
+///
+/// Values in `[1, highest]` are recorded with a fixed relative precision of
+/// `sig_digits` significant figures. The value range is partitioned into
+/// exponentially growing buckets (one per power of two), each split into a
+/// linear array of sub-buckets, so recording a value is O(1) and percentile
+/// queries walk the per-index counts in value order. Two histograms created
+/// with identical parameters can be merged by summing their counts.
+#[derive(Clone)]
+struct Histogram {
+    highest: u64,
+    sub_bucket_half_count: usize,
+    sub_bucket_half_count_magnitude: u32,
+    sub_bucket_mask: u64,
+    counts: Vec<u64>,
+    total_count: u64,
+    max_value: u64,
+}
+
+impl Histogram {
+    /// Create a histogram tracking `[1, highest]` at `sig_digits` significant
+    /// figures of precision (3 ⇒ ~0.1% relative error).
+    fn new(highest: u64, sig_digits: u32) -> Self {
+        let largest_single_unit = 2 * 10u64.pow(sig_digits);
+        let sub_bucket_count_magnitude = (largest_single_unit as f64).log2().ceil() as u32;
+        let sub_bucket_count = 1usize << sub_bucket_count_magnitude;
+        let sub_bucket_half_count = sub_bucket_count / 2;
+        let sub_bucket_half_count_magnitude = sub_bucket_count_magnitude - 1;
+        let sub_bucket_mask = (sub_bucket_count as u64) - 1;
+
+        // Count how many buckets are needed to cover `highest`.
+        let mut smallest_untrackable = sub_bucket_count as u64;
+        let mut bucket_count = 1usize;
+        while smallest_untrackable <= highest.max(1) {
+            if smallest_untrackable > u64::MAX / 2 {
+                bucket_count += 1;
+                break;
+            }
+            smallest_untrackable <<= 1;
+            bucket_count += 1;
+        }
+
+        let counts_len = (bucket_count + 1) * sub_bucket_half_count;
+        Self {
+            highest,
+            sub_bucket_half_count,
+            sub_bucket_half_count_magnitude,
+            sub_bucket_mask,
+            counts: vec![0; counts_len],
+            total_count: 0,
+            max_value: 0,
+        }
+    }
+
+    fn bucket_index(&self, value: u64) -> u32 {
+        // Position of the highest set bit of `value | sub_bucket_mask`, minus
+        // the sub-bucket half-count magnitude: the power-of-two bucket the
+        // value lands in.
+        let leading = (value | self.sub_bucket_mask).leading_zeros();
+        63 - self.sub_bucket_half_count_magnitude - leading
+    }
+
+    fn sub_bucket_index(&self, value: u64, bucket_index: u32) -> usize {
+        (value >> bucket_index) as usize
+    }
+
+    fn counts_index(&self, bucket_index: u32, sub_bucket_index: usize) -> usize {
+        let bucket_base = (bucket_index as usize + 1) << self.sub_bucket_half_count_magnitude;
+        bucket_base + sub_bucket_index - self.sub_bucket_half_count
+    }
+
+    /// Record a single observation, clamping to the trackable range.
+    fn record(&mut self, value: u64) {
+        let value = value.clamp(1, self.highest);
+        let bucket_index = self.bucket_index(value);
+        let sub_bucket_index = self.sub_bucket_index(value, bucket_index);
+        let index = self.counts_index(bucket_index, sub_bucket_index);
+        self.counts[index] += 1;
+        self.total_count += 1;
+        if value > self.max_value {
+            self.max_value = value;
+        }
+    }
+
+    /// Merge another histogram into this one. Both must share identical
+    /// parameters (same `highest` and `sig_digits`).
+    fn merge(&mut self, other: &Histogram) {
+        for (dst, src) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *dst += *src;
+        }
+        self.total_count += other.total_count;
+        if other.max_value > self.max_value {
+            self.max_value = other.max_value;
+        }
+    }
+
+    /// Lowest value represented by a given counts-array index.
+    fn value_at_index(&self, index: usize) -> u64 {
+        let mut bucket_index = (index >> self.sub_bucket_half_count_magnitude) as i64 - 1;
+        let mut sub_bucket_index =
+            (index & (self.sub_bucket_half_count - 1)) + self.sub_bucket_half_count;
+        if bucket_index < 0 {
+            sub_bucket_index -= self.sub_bucket_half_count;
+            bucket_index = 0;
+        }
+        (sub_bucket_index as u64) << bucket_index
+    }
+
+    /// Value at the given percentile (0.0..=100.0).
+    fn value_at_percentile(&self, percentile: f64) -> u64 {
+        if self.total_count == 0 {
+            return 0;
+        }
+        let target = ((percentile / 100.0) * self.total_count as f64).ceil() as u64;
+        let target = target.clamp(1, self.total_count);
+        let mut accumulated = 0u64;
+        for (index, &count) in self.counts.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= target {
+                return self.value_at_index(index);
+            }
+        }
+        self.max_value
+    }
+
+    /// Arithmetic mean of the recorded values, taking each index's lowest
+    /// representative value, so it is exact only to the histogram's precision.
+    fn mean(&self) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        let mut weighted = 0f64;
+        for (index, &count) in self.counts.iter().enumerate() {
+            if count > 0 {
+                weighted += self.value_at_index(index) as f64 * count as f64;
+            }
+        }
+        weighted / self.total_count as f64
+    }
+
+    fn max(&self) -> u64 {
+        self.max_value
+    }
+}
+
 use anyhow::Result;
 use azure_core::credentials::Secret;
 use azure_data_cosmos::CosmosClient;
 use azure_data_cosmos::clients::ContainerClient;
+use azure_data_cosmos::models::ContainerProperties;
 use clap::Parser;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
 use tokio::time::sleep;
 
+/// Highest latency the histograms can track, in nanoseconds (100 seconds).
+/// Values above this are clamped into the top bucket.
+const MAX_LATENCY_NS: u64 = 100_000_000_000;
+
+/// Significant figures of precision for the latency histograms.
+const LATENCY_SIG_DIGITS: u32 = 3;
+
+/// How many operations a worker records between publishing a histogram
+/// snapshot for the live metrics endpoint. Keeps the hot path lock-free apart
+/// from one cheap copy per interval.
+const METRICS_PUBLISH_INTERVAL: u64 = 256;
+
+/// Construct a latency histogram with the shared parameters used by every
+/// worker, so the per-worker histograms can be merged.
+fn latency_histogram() -> Histogram {
+    Histogram::new(MAX_LATENCY_NS, LATENCY_SIG_DIGITS)
+}
+
 // Well-known Cosmos DB Emulator key, not a secret.
 const EMULATOR_KEY: &str =
     "C2y6yDjf5/R+ob0N8A7Cgv30VRDJIWEHLM+4QDU5DE2nQ9nDuVTqobD4b8mGGyPMbIZnqyMsEcaGQy67XIw/Jw==";
@@ -58,15 +224,89 @@ enum Commands {
         /// Number of concurrent workers
         #[arg(short = 'w', long = "workers", default_value_t = num_cpus::get())]
         workers: usize,
+
+        /// Target aggregate throughput in ops/sec. When set, workers switch to
+        /// open-loop scheduling with coordinated-omission correction; when
+        /// absent, workers run an unthrottled closed loop.
+        #[arg(short = 'r', long = "rate", value_parser = parse_rate)]
+        rate: Option<f64>,
+
+        /// Workload to benchmark: point reads, point writes (upserts), or a
+        /// mixed read/write blend controlled by --read-ratio.
+        #[arg(long = "workload", value_enum, default_value_t = WorkloadKind::Read)]
+        workload: WorkloadKind,
+
+        /// Percentage of operations that are reads in the mixed workload
+        /// (0-100). Ignored for the read and write workloads.
+        #[arg(long = "read-ratio", default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+        read_ratio: u8,
+
+        /// Serve live counters in Prometheus text format on this address
+        /// (e.g. 127.0.0.1:9100) for the duration of the run.
+        #[arg(long = "metrics-addr")]
+        metrics_addr: Option<String>,
+    },
+
+    /// Seed the RandomDocs container with the documents the benchmark reads,
+    /// creating the database and container first if they do not exist
+    Provision {
+        /// Number of items to create (keyed `item0`..`item{count-1}`)
+        #[arg(short = 'i', long = "item-count", default_value = "10000")]
+        item_count: i32,
+
+        /// Number of partitions to spread the items across
+        #[arg(short = 'p', long = "partition-count", default_value = "10")]
+        partition_count: i32,
+
+        /// Number of concurrent writers
+        #[arg(short = 'w', long = "workers", default_value_t = num_cpus::get())]
+        workers: usize,
     },
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum WorkloadKind {
+    Read,
+    Write,
+    Mixed,
+}
+
+/// Parse a `--rate` value, rejecting anything that would make the open-loop
+/// schedule degenerate: the rate must be finite and strictly positive (a
+/// zero or negative interval yields NaN/infinite sleep durations).
+fn parse_rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value.parse().map_err(|_| format!("invalid number: {}", value))?;
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err("rate must be a finite number greater than 0".to_string())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct BenchmarkResults {
     total_ops: i64,
     elapsed_time_ms: u64,
     ops_per_second: f64,
-    latency_ms: f64,
+    latency_mean_ms: f64,
+    latency_p50_ms: f64,
+    latency_p90_ms: f64,
+    latency_p99_ms: f64,
+    latency_p999_ms: f64,
+    latency_max_ms: f64,
+}
+
+/// Tunable knobs for a point-read/write/mixed benchmark run, bundled so they
+/// can be threaded through without an unwieldy positional argument list.
+struct BenchmarkConfig {
+    item_count: i32,
+    duration_seconds: u64,
+    partition_count: i32,
+    workers: usize,
+    rate: Option<f64>,
+    workload: WorkloadKind,
+    read_ratio: u8,
+    metrics_addr: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -79,6 +319,83 @@ struct RandomDocsItem {
     random_number: i32,
 }
 
+impl RandomDocsItem {
+    /// Build a document for `item{index}` on `partition{index % partition_count}`
+    /// with a randomized payload.
+    fn random(index: i32, partition_count: i32) -> Self {
+        let mut rng = rand::rng();
+        Self {
+            id: format!("item{}", index),
+            partition_key: format!("partition{}", index % partition_count),
+            data: format!("{:016x}", rng.random::<u64>()),
+            random_number: rng.random(),
+        }
+    }
+}
+
+/// A unit of benchmarkable work. `execute_benchmark` is workload-agnostic: each
+/// worker repeatedly calls `execute` and records the latency of a single
+/// operation, so the timing path is shared across read, write and mixed traffic.
+trait Workload: Send + Sync {
+    fn execute<'a>(
+        &'a self,
+        container: &'a ContainerClient,
+    ) -> impl std::future::Future<Output = Result<()>> + Send + 'a;
+}
+
+/// Point reads of a random `item{index}`.
+struct PointReadWorkload {
+    item_count: i32,
+    partition_count: i32,
+}
+
+impl Workload for PointReadWorkload {
+    async fn execute(&self, container: &ContainerClient) -> Result<()> {
+        let index = rand::rng().random_range(0..self.item_count);
+        let item_id = format!("item{}", index);
+        let partition_key = format!("partition{}", index % self.partition_count);
+        container
+            .read_item::<RandomDocsItem>(&partition_key, &item_id, None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Point writes: upsert of a freshly generated `RandomDocsItem`.
+struct PointWriteWorkload {
+    item_count: i32,
+    partition_count: i32,
+}
+
+impl Workload for PointWriteWorkload {
+    async fn execute(&self, container: &ContainerClient) -> Result<()> {
+        let index = rand::rng().random_range(0..self.item_count);
+        let item = RandomDocsItem::random(index, self.partition_count);
+        container
+            .upsert_item(&item.partition_key, &item, None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Mixed read/write traffic: each operation is a read with probability
+/// `read_ratio / 100`, otherwise a write.
+struct MixedWorkload {
+    read: PointReadWorkload,
+    write: PointWriteWorkload,
+    read_ratio: u8,
+}
+
+impl Workload for MixedWorkload {
+    async fn execute(&self, container: &ContainerClient) -> Result<()> {
+        if rand::rng().random_range(0..100) < self.read_ratio {
+            self.read.execute(container).await
+        } else {
+            self.write.execute(container).await
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -89,13 +406,33 @@ async fn main() -> Result<()> {
             duration_seconds,
             partition_count,
             workers,
+            rate,
+            workload,
+            read_ratio,
+            metrics_addr,
         } => {
-            run_point_read_benchmark(
+            let config = BenchmarkConfig {
+                item_count,
+                duration_seconds,
+                partition_count,
+                workers,
+                rate,
+                workload,
+                read_ratio,
+                metrics_addr,
+            };
+            run_point_read_benchmark(&cli.endpoint, &cli.key, &cli.database, config).await?;
+        }
+        Commands::Provision {
+            item_count,
+            partition_count,
+            workers,
+        } => {
+            run_provision(
                 &cli.endpoint,
                 &cli.key,
                 &cli.database,
                 item_count,
-                duration_seconds,
                 partition_count,
                 workers,
             )
@@ -110,80 +447,179 @@ async fn run_point_read_benchmark(
     endpoint: &str,
     key: &str,
     database_name: &str,
-    item_count: i32,
-    duration_seconds: u64,
-    partition_count: i32,
-    workers: usize,
+    config: BenchmarkConfig,
 ) -> Result<()> {
+    let BenchmarkConfig {
+        item_count,
+        duration_seconds,
+        partition_count,
+        workers,
+        rate,
+        workload,
+        read_ratio,
+        metrics_addr,
+    } = config;
+
     // Create Cosmos client
     let credential = Secret::from(key.to_string());
     let client = CosmosClient::with_key(endpoint, credential, None)?;
     let database = client.database_client(database_name);
     let container = database.container_client("RandomDocs");
 
-    println!("Starting point read benchmark...");
+    let workload_name = match workload {
+        WorkloadKind::Read => "point read".to_string(),
+        WorkloadKind::Write => "point write".to_string(),
+        WorkloadKind::Mixed => format!("mixed ({}% reads)", read_ratio),
+    };
+
+    println!("Starting {} benchmark...", workload_name);
     println!("Item count: {}", item_count);
     println!("Duration: {}s", duration_seconds);
     println!("Partition count: {}", partition_count);
     println!("Workers: {}", workers);
+    match rate {
+        Some(rate) => println!("Target rate: {:.1} ops/sec (open loop)", rate),
+        None => println!("Target rate: unthrottled (closed loop)"),
+    }
     println!();
 
-    // Run benchmark
-    let results = execute_benchmark(
-        &container,
-        item_count,
-        partition_count,
-        workers,
-        Duration::from_secs(duration_seconds),
-    )
-    .await?;
+    // Short, label-safe workload name for metrics.
+    let workload_label = match workload {
+        WorkloadKind::Read => "read",
+        WorkloadKind::Write => "write",
+        WorkloadKind::Mixed => "mixed",
+    };
+
+    // Build the requested workload and run the (workload-agnostic) benchmark.
+    let duration = Duration::from_secs(duration_seconds);
+    let results = match workload {
+        WorkloadKind::Read => {
+            let workload = PointReadWorkload {
+                item_count,
+                partition_count,
+            };
+            execute_benchmark(
+                &container, workload, workers, duration, rate, workload_label, metrics_addr,
+            )
+            .await?
+        }
+        WorkloadKind::Write => {
+            let workload = PointWriteWorkload {
+                item_count,
+                partition_count,
+            };
+            execute_benchmark(
+                &container, workload, workers, duration, rate, workload_label, metrics_addr,
+            )
+            .await?
+        }
+        WorkloadKind::Mixed => {
+            let workload = MixedWorkload {
+                read: PointReadWorkload {
+                    item_count,
+                    partition_count,
+                },
+                write: PointWriteWorkload {
+                    item_count,
+                    partition_count,
+                },
+                read_ratio,
+            };
+            execute_benchmark(
+                &container, workload, workers, duration, rate, workload_label, metrics_addr,
+            )
+            .await?
+        }
+    };
 
     // Print results
-    print_results(&results);
+    print_results(&results, &workload_name);
 
     Ok(())
 }
 
-async fn execute_benchmark(
+async fn execute_benchmark<W: Workload + 'static>(
     container: &ContainerClient,
-    item_count: i32,
-    partition_count: i32,
+    workload: W,
     workers: usize,
     duration: Duration,
+    rate: Option<f64>,
+    workload_label: &str,
+    metrics_addr: Option<String>,
 ) -> Result<BenchmarkResults> {
+    let workload = Arc::new(workload);
     let start_time = Instant::now();
+    // Shared anchor for open-loop intended start times. All workers schedule
+    // their operations relative to this instant.
+    let schedule_anchor = tokio::time::Instant::now();
     println!(
         "Benchmark started at {} with {} workers",
         chrono::Utc::now().format("%H:%M:%S%.3f"),
         workers
     );
 
-    // Shared counters for all workers
+    // Shared ops counter for progress reporting; latencies are recorded into
+    // a per-worker histogram to avoid cross-worker contention.
     let total_ops = Arc::new(AtomicI64::new(0));
-    let total_latency_ns = Arc::new(AtomicI64::new(0));
 
     // Create cancellation token for clean shutdown
     let cancel_token = tokio_util::sync::CancellationToken::new();
 
+    // When a metrics endpoint is requested, give each worker its own snapshot
+    // slot. Workers publish a copy of their (lock-free) local histogram into it
+    // periodically, and the scrape handler merges those snapshots on demand, so
+    // the measurement path never takes a shared per-op lock.
+    let worker_snapshots: Vec<Arc<Mutex<Histogram>>> = if metrics_addr.is_some() {
+        (0..workers)
+            .map(|_| Arc::new(Mutex::new(latency_histogram())))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let metrics_handle = match metrics_addr {
+        Some(addr) => {
+            let labels = format!("workload=\"{}\",workers=\"{}\"", workload_label, workers);
+            let total_ops = total_ops.clone();
+            let cancel = cancel_token.clone();
+            let snapshots = worker_snapshots.clone();
+            Some(tokio::spawn(async move {
+                if let Err(e) =
+                    serve_metrics(addr, labels, total_ops, start_time, snapshots, cancel).await
+                {
+                    eprintln!("Metrics server error: {}", e);
+                }
+            }))
+        }
+        None => None,
+    };
+
     // Start workers
     let mut worker_handles = Vec::new();
     for worker_id in 0..workers {
         let container_clone = container.clone();
+        let workload_clone = workload.clone();
         let total_ops_clone = total_ops.clone();
-        let total_latency_clone = total_latency_ns.clone();
         let cancel_clone = cancel_token.clone();
+        let snapshot_clone = worker_snapshots.get(worker_id).cloned();
+
+        let schedule = rate.map(|rate| Schedule {
+            anchor: schedule_anchor,
+            rate,
+            workers,
+        });
 
         let handle = tokio::spawn(async move {
             worker_benchmark(
                 container_clone,
-                item_count,
-                partition_count,
+                workload_clone,
                 total_ops_clone,
-                total_latency_clone,
                 cancel_clone,
                 worker_id,
+                schedule,
+                snapshot_clone,
             )
-            .await;
+            .await
         });
 
         worker_handles.push(handle);
@@ -218,23 +654,32 @@ async fn execute_benchmark(
         }
     });
 
-    // Wait for benchmark duration
-    sleep(duration).await;
+    // Wait for the benchmark duration, or for an interrupt. Either way we
+    // cancel the workers and still report the measurements gathered so far.
+    tokio::select! {
+        _ = sleep(duration) => {}
+        _ = tokio::signal::ctrl_c() => {
+            println!("\nInterrupted, stopping workers and reporting partial results...");
+        }
+    }
 
     // Cancel all workers
     cancel_token.cancel();
 
-    // Wait for all workers to finish
+    // Wait for all workers to finish and merge their latency histograms.
+    let mut histogram = latency_histogram();
     for handle in worker_handles {
-        handle.await?;
+        histogram.merge(&handle.await?);
     }
 
-    // Cancel progress reporting
+    // Cancel progress reporting and the metrics server
     progress_handle.abort();
+    if let Some(metrics_handle) = metrics_handle {
+        metrics_handle.abort();
+    }
 
     let actual_elapsed = start_time.elapsed();
     let final_ops = total_ops.load(Ordering::Relaxed);
-    let final_latency_ns = total_latency_ns.load(Ordering::Relaxed);
 
     if final_ops == 0 {
         return Err(anyhow::anyhow!("No operations completed"));
@@ -244,76 +689,411 @@ async fn execute_benchmark(
         total_ops: final_ops,
         elapsed_time_ms: actual_elapsed.as_millis() as u64,
         ops_per_second: final_ops as f64 / actual_elapsed.as_secs_f64(),
-        latency_ms: (final_latency_ns as f64 / final_ops as f64) / 1_000_000.0, // Convert to ms
+        latency_mean_ms: histogram.mean() / 1_000_000.0,
+        latency_p50_ms: ns_to_ms(histogram.value_at_percentile(50.0)),
+        latency_p90_ms: ns_to_ms(histogram.value_at_percentile(90.0)),
+        latency_p99_ms: ns_to_ms(histogram.value_at_percentile(99.0)),
+        latency_p999_ms: ns_to_ms(histogram.value_at_percentile(99.9)),
+        latency_max_ms: ns_to_ms(histogram.max()),
     };
 
     Ok(results)
 }
 
-async fn worker_benchmark(
+/// Convert a nanosecond latency to fractional milliseconds.
+fn ns_to_ms(ns: u64) -> f64 {
+    ns as f64 / 1_000_000.0
+}
+
+/// Open-loop schedule shared across workers. Intended start times are spaced
+/// by `1/rate` across the whole fleet and interleaved per worker, so the
+/// aggregate offered load matches the target rate regardless of worker count.
+#[derive(Clone, Copy)]
+struct Schedule {
+    anchor: tokio::time::Instant,
+    rate: f64,
+    workers: usize,
+}
+
+impl Schedule {
+    /// Intended start time of the `op`-th operation issued by `worker_id`.
+    ///
+    /// Worker `w` owns the global slots `w, w + workers, w + 2*workers, ...`,
+    /// each `1/rate` seconds apart, so its own slots are `workers/rate` apart.
+    fn intended_start(&self, worker_id: usize, op: u64) -> tokio::time::Instant {
+        let slot = worker_id as f64 + op as f64 * self.workers as f64;
+        self.anchor + Duration::from_secs_f64(slot / self.rate)
+    }
+}
+
+async fn worker_benchmark<W: Workload>(
     container: ContainerClient,
-    item_count: i32,
-    partition_count: i32,
+    workload: Arc<W>,
     total_ops: Arc<AtomicI64>,
-    total_latency_ns: Arc<AtomicI64>,
     cancel_token: tokio_util::sync::CancellationToken,
     worker_id: usize,
-) {
+    schedule: Option<Schedule>,
+    snapshot: Option<Arc<Mutex<Histogram>>>,
+) -> Histogram {
+    let mut histogram = latency_histogram();
+    let mut op: u64 = 0;
+    // Operations recorded since we last published a snapshot for the metrics
+    // endpoint. Recording stays lock-free; we only take this worker's own lock
+    // once every `METRICS_PUBLISH_INTERVAL` ops to copy the latest histogram.
+    let mut ops_since_publish: u64 = 0;
     loop {
+        // In open-loop mode, wait until this operation's intended start time
+        // before issuing it. Latency is measured from that intended start so a
+        // late send still carries its queueing delay (coordinated-omission
+        // correction).
+        let intended_start = schedule.map(|s| s.intended_start(worker_id, op));
+        if let Some(intended_start) = intended_start {
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = tokio::time::sleep_until(intended_start) => {}
+            }
+        }
+
         tokio::select! {
             _ = cancel_token.cancelled() => {
                 break;
             }
             _ = async {
-                // Select random item ID
-                let item_index = rand::rng().random_range(0..item_count);
-                let item_id = format!("item{}", item_index);
-                let partition_key = format!("partition{}", item_index % partition_count);
-
-                // Measure point read latency
+                // Measure operation latency. In open-loop mode the clock starts
+                // at the intended start time, not the actual send time.
                 let op_start = Instant::now();
 
-                let result = container
-                    .read_item::<RandomDocsItem>(&partition_key, &item_id, None)
-                    .await;
+                let result = workload.execute(&container).await;
 
-                let op_latency = op_start.elapsed();
+                let op_latency = match intended_start {
+                    Some(intended_start) => tokio::time::Instant::now()
+                        .saturating_duration_since(intended_start),
+                    None => op_start.elapsed(),
+                };
 
                 match result {
                     Ok(_) => {
-                        // Successfully read item
+                        // Operation succeeded
                         total_ops.fetch_add(1, Ordering::Relaxed);
-                        total_latency_ns.fetch_add(op_latency.as_nanos() as i64, Ordering::Relaxed);
+                        let latency_ns = op_latency.as_nanos() as u64;
+                        histogram.record(latency_ns);
+                        if let Some(snapshot) = &snapshot {
+                            ops_since_publish += 1;
+                            if ops_since_publish >= METRICS_PUBLISH_INTERVAL {
+                                *snapshot.lock().unwrap() = histogram.clone();
+                                ops_since_publish = 0;
+                            }
+                        }
                     }
                     Err(e) => {
                         // Log error but don't stop the benchmark for individual failures
-                        eprintln!("Worker {}: Error reading item {}: {}", worker_id, item_id, e);
+                        eprintln!("Worker {}: Error during operation: {}", worker_id, e);
                     }
                 }
 
                 Ok::<(), anyhow::Error>(())
             } => {}
         }
+        op += 1;
+    }
+    // Publish a final snapshot so the last scrape reflects this worker's full
+    // run rather than its most recent checkpoint.
+    if let Some(snapshot) = &snapshot {
+        *snapshot.lock().unwrap() = histogram.clone();
+    }
+    histogram
+}
+
+async fn run_provision(
+    endpoint: &str,
+    key: &str,
+    database_name: &str,
+    item_count: i32,
+    partition_count: i32,
+    workers: usize,
+) -> Result<()> {
+    // Create Cosmos client
+    let credential = Secret::from(key.to_string());
+    let client = CosmosClient::with_key(endpoint, credential, None)?;
+
+    println!("Provisioning RandomDocs container...");
+    println!("Item count: {}", item_count);
+    println!("Partition count: {}", partition_count);
+    println!("Writers: {}", workers);
+    println!();
+
+    // Create the database and container if they are not already present. A
+    // conflict here just means someone already provisioned them, so we log and
+    // continue rather than fail the run.
+    if let Err(e) = client.create_database(database_name, None).await {
+        println!("Note: database not created ({}); assuming it already exists", e);
+    }
+    let database = client.database_client(database_name);
+
+    let properties = ContainerProperties {
+        id: "RandomDocs".into(),
+        partition_key: "/partitionKey".into(),
+        ..Default::default()
+    };
+    if let Err(e) = database.create_container(properties, None).await {
+        println!("Note: container not created ({}); assuming it already exists", e);
+    }
+    let container = database.container_client("RandomDocs");
+
+    // Bounded writer pool: each worker pulls the next index to insert from a
+    // shared counter until the keyspace is exhausted.
+    let start_time = Instant::now();
+    let next_index = Arc::new(AtomicI64::new(0));
+    let inserted = Arc::new(AtomicI64::new(0));
+
+    let mut handles = Vec::new();
+    for _ in 0..workers {
+        let container = container.clone();
+        let next_index = next_index.clone();
+        let inserted = inserted.clone();
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                if index >= item_count as i64 {
+                    break;
+                }
+
+                let item = RandomDocsItem::random(index as i32, partition_count);
+                match container.upsert_item(&item.partition_key, &item, None).await {
+                    Ok(_) => {
+                        inserted.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        eprintln!("Error upserting item{}: {}", index, e);
+                    }
+                }
+            }
+        });
+
+        handles.push(handle);
+    }
+
+    for handle in handles {
+        handle.await?;
+    }
+
+    let elapsed = start_time.elapsed();
+    let total_inserted = inserted.load(Ordering::Relaxed);
+    let throughput = total_inserted as f64 / elapsed.as_secs_f64();
+
+    println!();
+    println!("=== Provisioning Complete ===");
+    println!("Items inserted: {}", total_inserted);
+    println!("Elapsed: {}ms", elapsed.as_millis());
+    println!("Throughput: {:.2} items/sec", throughput);
+    println!("=============================");
+
+    Ok(())
+}
+
+/// Serve live benchmark counters in Prometheus text format until cancelled.
+/// Every connection receives the same scrape payload regardless of path.
+async fn serve_metrics(
+    addr: String,
+    labels: String,
+    total_ops: Arc<AtomicI64>,
+    start_time: Instant,
+    snapshots: Vec<Arc<Mutex<Histogram>>>,
+    cancel: tokio_util::sync::CancellationToken,
+) -> Result<()> {
+    let listener = TcpListener::bind(&addr).await?;
+    println!("Serving Prometheus metrics at http://{}/metrics", addr);
+
+    // Remember the previous scrape so ops/sec reflects the rate *between*
+    // scrapes rather than the converging cumulative average.
+    let mut last_sample: Option<(Instant, i64)> = None;
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => {
+                let (mut stream, _) = accepted?;
+                // Drain the request; we serve the same payload on any path.
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).await;
+
+                let now = Instant::now();
+                let ops = total_ops.load(Ordering::Relaxed);
+                let ops_per_sec = match last_sample {
+                    Some((prev_time, prev_ops)) => {
+                        let dt = now.duration_since(prev_time).as_secs_f64();
+                        if dt > 0.0 {
+                            (ops - prev_ops) as f64 / dt
+                        } else {
+                            0.0
+                        }
+                    }
+                    // First scrape: fall back to the average since start.
+                    None => {
+                        let elapsed = start_time.elapsed().as_secs_f64();
+                        if elapsed > 0.0 { ops as f64 / elapsed } else { 0.0 }
+                    }
+                };
+                last_sample = Some((now, ops));
+
+                // Merge each worker's latest published snapshot into a single
+                // view for this scrape.
+                let mut merged = latency_histogram();
+                for snapshot in &snapshots {
+                    merged.merge(&snapshot.lock().unwrap());
+                }
+
+                let body = render_metrics(&labels, ops, ops_per_sec, &merged);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        }
     }
+
+    Ok(())
+}
+
+/// Render the current counters as a Prometheus text-format scrape body.
+fn render_metrics(labels: &str, ops: i64, ops_per_sec: f64, histogram: &Histogram) -> String {
+    let quantile = |q: f64| ns_to_ms(histogram.value_at_percentile(q));
+
+    format!(
+        "# HELP rustbench_ops_total Total completed operations\n\
+         # TYPE rustbench_ops_total counter\n\
+         rustbench_ops_total{{{labels}}} {ops}\n\
+         # HELP rustbench_ops_per_second Throughput observed since the last scrape\n\
+         # TYPE rustbench_ops_per_second gauge\n\
+         rustbench_ops_per_second{{{labels}}} {ops_per_sec:.2}\n\
+         # HELP rustbench_latency_ms Operation latency quantiles in milliseconds\n\
+         # TYPE rustbench_latency_ms gauge\n\
+         rustbench_latency_ms{{{labels},quantile=\"0.5\"}} {p50:.3}\n\
+         rustbench_latency_ms{{{labels},quantile=\"0.9\"}} {p90:.3}\n\
+         rustbench_latency_ms{{{labels},quantile=\"0.99\"}} {p99:.3}\n\
+         rustbench_latency_ms{{{labels},quantile=\"0.999\"}} {p999:.3}\n\
+         # HELP rustbench_latency_max_ms Maximum observed latency in milliseconds\n\
+         # TYPE rustbench_latency_max_ms gauge\n\
+         rustbench_latency_max_ms{{{labels}}} {max:.3}\n",
+        labels = labels,
+        ops = ops,
+        ops_per_sec = ops_per_sec,
+        p50 = quantile(50.0),
+        p90 = quantile(90.0),
+        p99 = quantile(99.0),
+        p999 = quantile(99.9),
+        max = ns_to_ms(histogram.max()),
+    )
 }
 
-fn print_results(results: &BenchmarkResults) {
+fn print_results(results: &BenchmarkResults, workload_name: &str) {
     println!();
     println!("=== Benchmark Results ===");
     println!("Total ops: {}", results.total_ops);
     println!("Total elapsed time: {}ms", results.elapsed_time_ms);
     println!("Ops/sec: {:.2}", results.ops_per_second);
-    println!("Latency (mean): {:.2} ms", results.latency_ms);
+    println!("Latency (mean): {:.2} ms", results.latency_mean_ms);
+    println!("Latency (p50): {:.2} ms", results.latency_p50_ms);
+    println!("Latency (p90): {:.2} ms", results.latency_p90_ms);
+    println!("Latency (p99): {:.2} ms", results.latency_p99_ms);
+    println!("Latency (p99.9): {:.2} ms", results.latency_p999_ms);
+    println!("Latency (max): {:.2} ms", results.latency_max_ms);
     println!("========================");
 
     // Print markdown table for README
     println!();
-    println!("=== Markdown Table (Point Read Benchmark) ===");
-    println!("| Implementation | Total Ops | Duration (ms) | Ops/sec | Latency (ms) |");
-    println!("|---------------|-----------|---------------|---------|--------------|");
+    println!("=== Markdown Table ({} benchmark) ===", workload_name);
+    println!(
+        "| Implementation | Total Ops | Duration (ms) | Ops/sec | Mean (ms) | p50 (ms) | p90 (ms) | p99 (ms) | p99.9 (ms) | Max (ms) |"
+    );
+    println!(
+        "|---------------|-----------|---------------|---------|-----------|----------|----------|----------|------------|----------|"
+    );
     println!(
-        "| Rust | {} | {} | {:.2} | {:.2} |",
-        results.total_ops, results.elapsed_time_ms, results.ops_per_second, results.latency_ms
+        "| Rust | {} | {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} |",
+        results.total_ops,
+        results.elapsed_time_ms,
+        results.ops_per_second,
+        results.latency_mean_ms,
+        results.latency_p50_ms,
+        results.latency_p90_ms,
+        results.latency_p99_ms,
+        results.latency_p999_ms,
+        results.latency_max_ms,
     );
     println!("============================================");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_index_roundtrip() {
+        let histogram = latency_histogram();
+        // Sample values straddling several bucket boundaries (powers of two
+        // around the sub-bucket count) plus some large latencies.
+        let values = [
+            1u64, 2, 100, 1023, 1024, 1025, 2047, 2048, 4095, 4096, 1_000_000, 50_000_000,
+            99_999_999_999,
+        ];
+        for value in values {
+            let bucket_index = histogram.bucket_index(value);
+            let sub_bucket_index = histogram.sub_bucket_index(value, bucket_index);
+            let index = histogram.counts_index(bucket_index, sub_bucket_index);
+            let recovered = histogram.value_at_index(index);
+
+            // The index's representative value never exceeds the input, and is
+            // within the histogram's 3-significant-digit relative precision.
+            assert!(recovered <= value, "recovered {recovered} > value {value}");
+            let rel_error = (value - recovered) as f64 / value as f64;
+            assert!(rel_error < 0.001, "value {value} recovered {recovered} rel {rel_error}");
+        }
+    }
+
+    #[test]
+    fn percentiles_on_known_distribution() {
+        // 1..=100 all fall in the first bucket, so they are recorded exactly.
+        let mut histogram = Histogram::new(1000, 3);
+        for value in 1..=100u64 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.value_at_percentile(50.0), 50);
+        assert_eq!(histogram.value_at_percentile(99.0), 99);
+        assert_eq!(histogram.value_at_percentile(100.0), 100);
+    }
+
+    #[test]
+    fn merge_matches_single_histogram() {
+        let left_values = [5u64, 10, 100, 12_345, 678_901];
+        let right_values = [7u64, 99, 54_321, 9_000_000];
+
+        let mut left = latency_histogram();
+        let mut right = latency_histogram();
+        let mut combined = latency_histogram();
+        for value in left_values {
+            left.record(value);
+            combined.record(value);
+        }
+        for value in right_values {
+            right.record(value);
+            combined.record(value);
+        }
+
+        left.merge(&right);
+
+        // Merging equals recording every value into one histogram.
+        assert_eq!(left.total_count, combined.total_count);
+        assert_eq!(left.counts, combined.counts);
+        assert_eq!(left.max(), combined.max());
+        for quantile in [50.0, 90.0, 99.0, 99.9] {
+            assert_eq!(
+                left.value_at_percentile(quantile),
+                combined.value_at_percentile(quantile),
+            );
+        }
+    }
+}